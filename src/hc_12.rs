@@ -6,11 +6,18 @@ use esp_idf_svc::hal::peripheral::Peripheral;
 use esp_idf_svc::hal::uart::{self, Uart, UartDriver};
 
 use anyhow::Result;
+use embedded_io::{ErrorKind, ErrorType};
 use esp_idf_svc::hal::units::Hertz;
 use esp_idf_svc::sys::EspError;
 
 #[derive(thiserror::Error, Debug)]
-enum Hc12Error {
+pub enum Hc12Error {
+    #[error(transparent)]
+    Esp(#[from] EspError),
+
+    #[error("The device is in command mode and cannot be used for data transfer")]
+    CommandMode,
+
     #[error("Test command did not return OK")]
     Test,
     #[error("Failed to set the requested baud rate")]
@@ -20,13 +27,45 @@ enum Hc12Error {
     #[error("Failed to set the requested transmission mode")]
     TransmissionMode,
 
+    #[error("Failed to set the requested serial format")]
+    SerialFormat,
+
+    #[error("Channel must be between 1 and 127, got {0}")]
+    InvalidChannel(u8),
+
+    #[error("Failed to set the requested channel")]
+    Channel,
+
+    #[error("Failed to set the requested transmit power")]
+    Power,
+
+    #[error("Failed to parse a response from the device")]
+    Parse,
+
+    #[error("Timed out waiting for a response")]
+    Timeout,
+
+    #[error("The device is sleeping; call wake() first")]
+    Sleeping,
+
     #[error("Failed reset")]
     Default,
 }
 
+impl embedded_io::Error for Hc12Error {
+    fn kind(&self) -> ErrorKind {
+        match self {
+            Hc12Error::Esp(_) => ErrorKind::Other,
+            _ => ErrorKind::InvalidData,
+        }
+    }
+}
+
+#[derive(Default, Clone, Copy)]
 pub enum TransmissionMode {
     Fu1,
     Fu2,
+    #[default]
     Fu3,
     Fu4,
 }
@@ -53,6 +92,20 @@ impl From<TransmissionMode> for u32 {
     }
 }
 
+impl TryFrom<u32> for TransmissionMode {
+    type Error = Hc12Error;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        match value {
+            1 => Ok(TransmissionMode::Fu1),
+            2 => Ok(TransmissionMode::Fu2),
+            3 => Ok(TransmissionMode::Fu3),
+            4 => Ok(TransmissionMode::Fu4),
+            _ => Err(Hc12Error::Parse),
+        }
+    }
+}
+
 #[derive(Default, Clone, Copy)]
 pub enum BaudRate {
     Baud1200,
@@ -123,6 +176,187 @@ impl From<&BaudRate> for Hertz {
     }
 }
 
+impl TryFrom<u32> for BaudRate {
+    type Error = Hc12Error;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        match value {
+            1200 => Ok(BaudRate::Baud1200),
+            2400 => Ok(BaudRate::Baud2400),
+            4800 => Ok(BaudRate::Baud4800),
+            9600 => Ok(BaudRate::Baud9600),
+            19200 => Ok(BaudRate::Baud19200),
+            38400 => Ok(BaudRate::Baud38400),
+            57600 => Ok(BaudRate::Baud57600),
+            115200 => Ok(BaudRate::Baud115200),
+            _ => Err(Hc12Error::Parse),
+        }
+    }
+}
+
+#[derive(Default, Clone, Copy)]
+pub enum DataBits {
+    Seven,
+    #[default]
+    Eight,
+}
+
+impl From<&DataBits> for u8 {
+    fn from(data_bits: &DataBits) -> Self {
+        match data_bits {
+            DataBits::Seven => 7,
+            DataBits::Eight => 8,
+        }
+    }
+}
+
+impl From<&DataBits> for uart::config::DataBits {
+    fn from(data_bits: &DataBits) -> Self {
+        match data_bits {
+            DataBits::Seven => uart::config::DataBits::DataBits7,
+            DataBits::Eight => uart::config::DataBits::DataBits8,
+        }
+    }
+}
+
+#[derive(Default, Clone, Copy)]
+pub enum Parity {
+    #[default]
+    None,
+    Odd,
+    Even,
+}
+
+impl From<&Parity> for char {
+    fn from(parity: &Parity) -> Self {
+        match parity {
+            Parity::None => 'N',
+            Parity::Odd => 'O',
+            Parity::Even => 'E',
+        }
+    }
+}
+
+impl From<&Parity> for uart::config::Parity {
+    fn from(parity: &Parity) -> Self {
+        match parity {
+            Parity::None => uart::config::Parity::ParityNone,
+            Parity::Odd => uart::config::Parity::ParityOdd,
+            Parity::Even => uart::config::Parity::ParityEven,
+        }
+    }
+}
+
+#[derive(Default, Clone, Copy)]
+pub enum StopBits {
+    #[default]
+    One,
+    Two,
+}
+
+impl From<&StopBits> for u8 {
+    fn from(stop_bits: &StopBits) -> Self {
+        match stop_bits {
+            StopBits::One => 1,
+            StopBits::Two => 2,
+        }
+    }
+}
+
+impl From<&StopBits> for uart::config::StopBits {
+    fn from(stop_bits: &StopBits) -> Self {
+        match stop_bits {
+            StopBits::One => uart::config::StopBits::STOP1,
+            StopBits::Two => uart::config::StopBits::STOP2,
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct Channel(u8);
+
+impl Channel {
+    pub fn new(channel: u8) -> Result<Self> {
+        if !(1..=127).contains(&channel) {
+            return Err(Hc12Error::InvalidChannel(channel).into());
+        }
+
+        Ok(Self(channel))
+    }
+
+    /// The carrier frequency this channel tunes to, per the module's `433.4 + (n - 1) * 0.4` formula.
+    pub fn frequency_mhz(&self) -> f32 {
+        433.4 + (self.0 - 1) as f32 * 0.4
+    }
+}
+
+#[derive(Clone, Copy)]
+pub enum TransmitPower {
+    Level1,
+    Level2,
+    Level3,
+    Level4,
+    Level5,
+    Level6,
+    Level7,
+    Level8,
+}
+
+impl From<&TransmitPower> for u8 {
+    fn from(power: &TransmitPower) -> Self {
+        match power {
+            TransmitPower::Level1 => 1,
+            TransmitPower::Level2 => 2,
+            TransmitPower::Level3 => 3,
+            TransmitPower::Level4 => 4,
+            TransmitPower::Level5 => 5,
+            TransmitPower::Level6 => 6,
+            TransmitPower::Level7 => 7,
+            TransmitPower::Level8 => 8,
+        }
+    }
+}
+
+impl From<&TransmitPower> for i8 {
+    fn from(power: &TransmitPower) -> Self {
+        match power {
+            TransmitPower::Level1 => -1,
+            TransmitPower::Level2 => 2,
+            TransmitPower::Level3 => 5,
+            TransmitPower::Level4 => 8,
+            TransmitPower::Level5 => 11,
+            TransmitPower::Level6 => 14,
+            TransmitPower::Level7 => 17,
+            TransmitPower::Level8 => 20,
+        }
+    }
+}
+
+impl TryFrom<i8> for TransmitPower {
+    type Error = Hc12Error;
+
+    fn try_from(value: i8) -> Result<Self, Self::Error> {
+        match value {
+            -1 => Ok(TransmitPower::Level1),
+            2 => Ok(TransmitPower::Level2),
+            5 => Ok(TransmitPower::Level3),
+            8 => Ok(TransmitPower::Level4),
+            11 => Ok(TransmitPower::Level5),
+            14 => Ok(TransmitPower::Level6),
+            17 => Ok(TransmitPower::Level7),
+            20 => Ok(TransmitPower::Level8),
+            _ => Err(Hc12Error::Parse),
+        }
+    }
+}
+
+pub struct Hc12Config {
+    pub baud: BaudRate,
+    pub channel: Channel,
+    pub power: TransmitPower,
+    pub mode: TransmissionMode,
+}
+
 pub struct Command<'d, 'h> {
     hc_12: &'h mut Hc12<'d>,
 }
@@ -151,15 +385,44 @@ impl<'d, 'h> Command<'d, 'h> {
     }
 
     fn send_command(&mut self, command: &str) -> Result<String> {
-        let mut buffer = [0u8; 14];
-        self.hc_12.driver.clear_rx()?;
+        const CHUNK_TIMEOUT_MS: u32 = 20;
+        // Multi-line responses (e.g. AT+RX) arrive as several CRLF-terminated lines in quick
+        // succession; only treat the response as complete once nothing new has arrived for a
+        // couple of chunk reads, rather than on the first line that happens to end in "OK\r\n".
+        const IDLE_CHUNKS_BEFORE_COMPLETE: u32 = 2;
 
+        self.hc_12.driver.clear_rx()?;
         self.hc_12.write(command.as_bytes())?;
-        FreeRtos::delay_ms(200);
 
-        let bytes_read = self.hc_12.read(&mut buffer, 200)?;
+        let deadline = Instant::now() + self.hc_12.response_timeout;
+        let mut buffer = Vec::new();
+        let mut chunk = [0u8; 32];
+        let mut idle_chunks = 0;
+
+        loop {
+            let time_left = deadline
+                .checked_duration_since(Instant::now())
+                .ok_or(Hc12Error::Timeout)?;
+
+            let chunk_timeout = CHUNK_TIMEOUT_MS.min(time_left.as_millis() as u32);
+            let bytes_read = self.hc_12.read(&mut chunk, chunk_timeout)?;
+
+            if bytes_read == 0 {
+                idle_chunks += 1;
+            } else {
+                buffer.extend_from_slice(&chunk[..bytes_read]);
+                idle_chunks = 0;
+            }
+
+            if Self::response_complete(&buffer) && idle_chunks >= IDLE_CHUNKS_BEFORE_COMPLETE {
+                return Ok(String::from_utf8_lossy(&buffer).into_owned());
+            }
+        }
+    }
 
-        Ok(String::from_utf8_lossy(&buffer[..bytes_read]).into_owned())
+    /// A response is complete once it ends on a line terminator and contains at least one `OK`.
+    fn response_complete(buffer: &[u8]) -> bool {
+        buffer.ends_with(b"\r\n") && buffer.windows(2).any(|window| window == b"OK")
     }
 
     pub fn test(&mut self) -> Result<()> {
@@ -185,6 +448,7 @@ impl<'d, 'h> Command<'d, 'h> {
             self.hc_12.driver.change_baudrate(u32::from(baud_rate))?;
 
             if self.test().is_ok() {
+                self.hc_12.baud_rate = baud_rate;
                 return Ok(baud_rate);
             }
         }
@@ -201,6 +465,7 @@ impl<'d, 'h> Command<'d, 'h> {
         }
 
         self.hc_12.driver.change_baudrate(u32::from(baud_rate))?;
+        self.hc_12.baud_rate = *baud_rate;
 
         Ok(())
     }
@@ -215,14 +480,109 @@ impl<'d, 'h> Command<'d, 'h> {
 
         if let Some(new_baud_rate) = result.split(",").nth(1) {
             let new_baud_rate = new_baud_rate[1..].trim();
-            self.hc_12
-                .driver
-                .change_baudrate(str::parse::<u32>(new_baud_rate)?)?;
+            let new_baud_rate = str::parse::<u32>(new_baud_rate)?;
+            self.hc_12.driver.change_baudrate(new_baud_rate)?;
+            self.hc_12.baud_rate = BaudRate::try_from(new_baud_rate)?;
         }
 
+        self.hc_12.transmission_mode = *transmission_mode;
+
         Ok(())
     }
 
+    pub fn set_serial_format(
+        mut self,
+        data_bits: &DataBits,
+        parity: &Parity,
+        stop_bits: &StopBits,
+    ) -> Result<()> {
+        let format = format!(
+            "{}{}{}",
+            u8::from(data_bits),
+            char::from(parity),
+            u8::from(stop_bits)
+        );
+        let result = self.send_command(&format!("AT+U{format}"))?;
+
+        if result != format!("OK+U{format}\r\n") {
+            return Err(Hc12Error::SerialFormat.into());
+        }
+
+        self.hc_12.driver.change_data_bits(data_bits.into())?;
+        self.hc_12.driver.change_parity(parity.into())?;
+        self.hc_12.driver.change_stop_bits(stop_bits.into())?;
+
+        self.hc_12.data_bits = *data_bits;
+        self.hc_12.parity = *parity;
+        self.hc_12.stop_bits = *stop_bits;
+
+        Ok(())
+    }
+
+    pub fn set_channel(mut self, channel: &Channel) -> Result<()> {
+        let command = format!("AT+C{:03}", channel.0);
+        let result = self.send_command(&command)?;
+
+        if result != format!("OK+C{:03}\r\n", channel.0) {
+            return Err(Hc12Error::Channel.into());
+        }
+
+        Ok(())
+    }
+
+    pub fn set_power(mut self, power: &TransmitPower) -> Result<()> {
+        let command = format!("AT+P{}", u8::from(power));
+        let result = self.send_command(&command)?;
+
+        if result != format!("OK+P{}\r\n", u8::from(power)) {
+            return Err(Hc12Error::Power.into());
+        }
+
+        Ok(())
+    }
+
+    pub fn read_parameters(&mut self) -> Result<Hc12Config> {
+        let result = self.send_command("AT+RX")?;
+
+        let mut baud = None;
+        let mut channel = None;
+        let mut power = None;
+        let mut mode = None;
+
+        for line in result.lines() {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            } else if let Some(value) = line.strip_prefix("OK+B") {
+                baud = Some(BaudRate::try_from(
+                    value.parse::<u32>().map_err(|_| Hc12Error::Parse)?,
+                )?);
+            } else if let Some(value) = line.strip_prefix("OK+RC") {
+                channel = Some(Channel::new(
+                    value.parse::<u8>().map_err(|_| Hc12Error::Parse)?,
+                )?);
+            } else if let Some(value) = line.strip_prefix("OK+RP:") {
+                let value = value.trim_start_matches('+').trim_end_matches("dBm");
+                power = Some(TransmitPower::try_from(
+                    value.parse::<i8>().map_err(|_| Hc12Error::Parse)?,
+                )?);
+            } else if let Some(value) = line.strip_prefix("OK+FU") {
+                mode = Some(TransmissionMode::try_from(
+                    value.parse::<u32>().map_err(|_| Hc12Error::Parse)?,
+                )?);
+            } else {
+                return Err(Hc12Error::Parse.into());
+            }
+        }
+
+        Ok(Hc12Config {
+            baud: baud.ok_or(Hc12Error::Parse)?,
+            channel: channel.ok_or(Hc12Error::Parse)?,
+            power: power.ok_or(Hc12Error::Parse)?,
+            mode: mode.ok_or(Hc12Error::Parse)?,
+        })
+    }
+
     pub fn set_default(&mut self) -> Result<()> {
         let result = self.send_command("AT+DEFAULT")?;
 
@@ -230,6 +590,22 @@ impl<'d, 'h> Command<'d, 'h> {
             return Err(Hc12Error::Default.into());
         }
 
+        self.hc_12.data_bits = DataBits::default();
+        self.hc_12.parity = Parity::default();
+        self.hc_12.stop_bits = StopBits::default();
+        self.hc_12.transmission_mode = TransmissionMode::default();
+        self.hc_12.baud_rate = BaudRate::default();
+        self.hc_12
+            .driver
+            .change_data_bits((&self.hc_12.data_bits).into())?;
+        self.hc_12.driver.change_parity((&self.hc_12.parity).into())?;
+        self.hc_12
+            .driver
+            .change_stop_bits((&self.hc_12.stop_bits).into())?;
+        self.hc_12
+            .driver
+            .change_baudrate(u32::from(self.hc_12.baud_rate))?;
+
         Ok(())
     }
 }
@@ -239,6 +615,17 @@ pub struct Hc12<'d> {
     set: PinDriver<'d, AnyOutputPin, Output>,
 
     last_command_exit: Instant,
+    response_timeout: Duration,
+
+    data_bits: DataBits,
+    parity: Parity,
+    stop_bits: StopBits,
+
+    transmission_mode: TransmissionMode,
+    baud_rate: BaudRate,
+    is_sleeping: bool,
+    pre_sleep_transmission_mode: TransmissionMode,
+    pre_sleep_baud_rate: BaudRate,
 }
 
 impl<'d> Hc12<'d> {
@@ -268,10 +655,20 @@ impl<'d> Hc12<'d> {
             driver,
             set,
             last_command_exit,
+            response_timeout: Duration::from_millis(500),
+            data_bits: DataBits::default(),
+            parity: Parity::default(),
+            stop_bits: StopBits::default(),
+            transmission_mode: TransmissionMode::default(),
+            baud_rate: BaudRate::default(),
+            is_sleeping: false,
+            pre_sleep_transmission_mode: TransmissionMode::default(),
+            pre_sleep_baud_rate: BaudRate::default(),
         };
 
         if let Some(baud_rate) = &baud_rate {
             hc_12.driver.change_baudrate(baud_rate)?;
+            hc_12.baud_rate = *baud_rate;
         } else {
             hc_12.command()?.auto_baud()?;
         }
@@ -280,14 +677,124 @@ impl<'d> Hc12<'d> {
     }
 
     pub fn command<'h>(&'h mut self) -> Result<Command<'d, 'h>> {
+        if self.is_sleeping {
+            return Err(Hc12Error::Sleeping.into());
+        }
+
         Command::new(self)
     }
 
-    pub fn read(&self, buf: &mut [u8], timeout: u32) -> Result<usize, EspError> {
+    /// Overrides how long a [`Command`] waits for a full response before returning [`Hc12Error::Timeout`].
+    pub fn set_response_timeout(&mut self, timeout: Duration) {
+        self.response_timeout = timeout;
+    }
+
+    /// Enters the low-power FU2 mode and holds `SET` low to minimize quiescent current.
+    ///
+    /// The previously active [`TransmissionMode`] and [`BaudRate`] are recorded so [`Hc12::wake`]
+    /// can restore them; `transmission_mode`/`baud_rate` themselves keep reflecting the device's
+    /// live configuration (FU2, and whatever baud rate it reports) while asleep.
+    pub fn sleep(&mut self) -> Result<()> {
+        if self.is_sleeping {
+            return Ok(());
+        }
+
+        self.pre_sleep_transmission_mode = self.transmission_mode;
+        self.pre_sleep_baud_rate = self.baud_rate;
+
+        Command::new(self)?.set_transmission_mode(&TransmissionMode::Fu2)?;
+
+        self.set.set_low()?;
+        self.is_sleeping = true;
+
+        Ok(())
+    }
+
+    /// Leaves low-power mode, restoring the [`TransmissionMode`] and [`BaudRate`] active before [`Hc12::sleep`].
+    ///
+    /// `is_sleeping` is only cleared once both commands succeed, so a failed `wake` can be
+    /// retried without `command()`/data-mode calls being let through against a half-restored device.
+    pub fn wake(&mut self) -> Result<()> {
+        if !self.is_sleeping {
+            return Ok(());
+        }
+
+        let transmission_mode = self.pre_sleep_transmission_mode;
+        let baud_rate = self.pre_sleep_baud_rate;
+
+        Command::new(self)?.set_transmission_mode(&transmission_mode)?;
+        Command::new(self)?.set_baud(&baud_rate)?;
+
+        self.is_sleeping = false;
+
+        Ok(())
+    }
+
+    /// Raw, unguarded access to the underlying UART for [`Command::send_command`] to use while
+    /// `set` is held low. Not exposed outside the crate: external callers must go through the
+    /// [`embedded_io::Read`]/[`embedded_io::Write`] impls below, which enforce the command-mode
+    /// and sleep guards.
+    pub(crate) fn read(&self, buf: &mut [u8], timeout: u32) -> Result<usize, EspError> {
         self.driver.read(buf, timeout)
     }
 
-    pub fn write(&self, buf: &[u8]) -> Result<usize, EspError> {
+    pub(crate) fn write(&self, buf: &[u8]) -> Result<usize, EspError> {
         self.driver.write(buf)
     }
 }
+
+impl ErrorType for Hc12<'_> {
+    type Error = Hc12Error;
+}
+
+impl embedded_io::Read for Hc12<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        if self.is_sleeping {
+            return Err(Hc12Error::Sleeping);
+        }
+        if self.set.is_set_low() {
+            return Err(Hc12Error::CommandMode);
+        }
+
+        Ok(self.driver.read(buf, esp_idf_svc::hal::delay::BLOCK)?)
+    }
+}
+
+impl embedded_io::Write for Hc12<'_> {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        if self.is_sleeping {
+            return Err(Hc12Error::Sleeping);
+        }
+        if self.set.is_set_low() {
+            return Err(Hc12Error::CommandMode);
+        }
+
+        Ok(self.driver.write(buf)?)
+    }
+
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        if self.is_sleeping {
+            return Err(Hc12Error::Sleeping);
+        }
+        if self.set.is_set_low() {
+            return Err(Hc12Error::CommandMode);
+        }
+
+        Ok(self.driver.flush()?)
+    }
+}
+
+impl std::fmt::Write for Hc12<'_> {
+    fn write_str(&mut self, s: &str) -> std::fmt::Result {
+        let mut remaining = s.as_bytes();
+        while !remaining.is_empty() {
+            let written = embedded_io::Write::write(self, remaining).map_err(|_| std::fmt::Error)?;
+            if written == 0 {
+                return Err(std::fmt::Error);
+            }
+            remaining = &remaining[written..];
+        }
+
+        Ok(())
+    }
+}